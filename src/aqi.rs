@@ -1,10 +1,12 @@
 use lazy_static;
 
+use std::fmt;
+
 
 type AirQualityRow = (f32, f32, i32, i32, &'static str);
 
 lazy_static! {
-    /// A lookup table of concentration high, low, index high, low, and
+    /// A lookup table of concentration low, high, index low, high, and
     /// label based on EPA guidance for PM2.5 pollution
     static ref PM2_5_LOOKUP_TABLE: Vec<AirQualityRow> = vec![
         (0.0,   12.0,  0,   50,  "good"),
@@ -15,18 +17,77 @@ lazy_static! {
         (250.5, 350.4, 301, 400, "hazardous"),
         (350.5, 500.4, 401, 500, "hazardous"),
     ];
+
+    /// A lookup table of concentration low, high, index low, high, and
+    /// label based on EPA guidance for PM10 pollution
+    static ref PM10_LOOKUP_TABLE: Vec<AirQualityRow> = vec![
+        (0.0,   54.0,  0,   50,  "good"),
+        (55.0,  154.0, 51,  100, "moderate"),
+        (155.0, 254.0, 101, 150, "unhealthy for sensitive groups"),
+        (255.0, 354.0, 151, 200, "unhealthy"),
+        (355.0, 424.0, 201, 300, "very unhealthy"),
+        (425.0, 604.0, 301, 500, "hazardous"),
+    ];
+}
+
+/// A pollutant the EPA defines an Air Quality Index over. Only the particulate
+/// pollutants reported by the SDS011 sensor are filled in; the gaseous ones
+/// (O3, CO, SO2, NO2) are left as hooks until a sensor reports them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pollutant {
+    Pm2_5,
+    Pm10,
+}
+
+impl Pollutant {
+    /// The breakpoint table for this pollutant.
+    fn lookup_table(&self) -> &'static [AirQualityRow] {
+        match self {
+            Pollutant::Pm2_5 => &PM2_5_LOOKUP_TABLE,
+            Pollutant::Pm10 => &PM10_LOOKUP_TABLE,
+        }
+    }
+
+    /// The number of decimal places a concentration is truncated to before it
+    /// is looked up. PM2.5 breakpoints are defined to a tenth, PM10 to a whole
+    /// microgram.
+    fn precision(&self) -> u32 {
+        match self {
+            Pollutant::Pm2_5 => 1,
+            Pollutant::Pm10 => 0,
+        }
+    }
 }
 
-/// Finds the breakpoints using the air quality table
-fn find_lookup_values(concentration: f32) -> AirQualityRow {
-    if concentration > 500.4 {
-        return *PM2_5_LOOKUP_TABLE.last().unwrap()
+impl fmt::Display for Pollutant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Pollutant::Pm2_5 => "PM2.5",
+            Pollutant::Pm10 => "PM10",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Truncates a concentration to the pollutant's breakpoint precision, as the
+/// EPA requires before a table lookup (e.g. PM2.5 to a tenth).
+fn truncate(concentration: f32, precision: u32) -> f32 {
+    let factor = 10i32.pow(precision) as f32;
+    (concentration * factor).trunc() / factor
+}
+
+/// Finds the breakpoints for a concentration in the given table, clamping
+/// values above the top breakpoint to the last row.
+fn find_lookup_values(table: &[AirQualityRow], concentration: f32) -> AirQualityRow {
+    let last = *table.last().unwrap();
+    if concentration > last.1 {
+        return last
     }
 
     // TODO is there a nicer way to do this without an intermediate
     // Option type?
     let mut row = None;
-    for r in PM2_5_LOOKUP_TABLE.iter() {
+    for r in table.iter() {
         let (low, high, _, _, _) = r;
         if concentration >= *low && concentration <= *high {
             row = Some(*r);
@@ -44,9 +105,34 @@ fn aqi(lookup_values: AirQualityRow, concentration: f32) -> f32 {
         + i_low as f32
 }
 
-pub fn aqi_from_pm2_5(concentration: f32) -> f32 {
-    let lookup_values = find_lookup_values(concentration);
-    aqi(lookup_values, concentration)
+/// A computed sub-index for a single pollutant, rounded to the nearest
+/// integer as the EPA reports it, along with its category label.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SubIndex {
+    pub pollutant: Pollutant,
+    pub aqi: i32,
+    pub category: &'static str,
+}
+
+/// Computes the AQI sub-index for a single pollutant.
+pub fn sub_index(pollutant: Pollutant, concentration: f32) -> SubIndex {
+    let truncated = truncate(concentration, pollutant.precision());
+    let lookup_values = find_lookup_values(pollutant.lookup_table(), truncated);
+    SubIndex {
+        pollutant,
+        aqi: aqi(lookup_values, truncated).round() as i32,
+        category: lookup_values.4,
+    }
+}
+
+/// Computes the overall AQI as the maximum of the available per-pollutant
+/// sub-indices, returning the "responsible" pollutant and its category.
+/// Returns `None` when no concentrations are supplied.
+pub fn overall_aqi(concentrations: &[(Pollutant, f32)]) -> Option<SubIndex> {
+    concentrations
+        .iter()
+        .map(|(pollutant, concentration)| sub_index(*pollutant, *concentration))
+        .max_by_key(|s| s.aqi)
 }
 
 #[cfg(test)]
@@ -55,10 +141,32 @@ mod test_aqi {
 
     #[test]
     fn test_aqi() {
-        let result = aqi_from_pm2_5(12.0);
-        assert_eq!(50.0, result);
+        let result = sub_index(Pollutant::Pm2_5, 12.0);
+        assert_eq!(50, result.aqi);
+
+        let result = sub_index(Pollutant::Pm2_5, 0.0);
+        assert_eq!(0, result.aqi);
+    }
 
-        let result = aqi_from_pm2_5(0.0);
-        assert_eq!(0.0, result);
+    #[test]
+    fn test_sub_index_pm10() {
+        // 54 -> top of the "good" row maps to 50.
+        let result = sub_index(Pollutant::Pm10, 54.0);
+        assert_eq!(50, result.aqi);
+        assert_eq!("good", result.category);
+    }
+
+    #[test]
+    fn test_overall_aqi_picks_max() {
+        let result = overall_aqi(&[(Pollutant::Pm2_5, 12.0), (Pollutant::Pm10, 155.0)])
+            .unwrap();
+        assert_eq!(Pollutant::Pm10, result.pollutant);
+        assert_eq!(101, result.aqi);
+    }
+
+    #[test]
+    fn test_clamps_above_top_breakpoint() {
+        let result = sub_index(Pollutant::Pm10, 9999.0);
+        assert_eq!("hazardous", result.category);
     }
 }
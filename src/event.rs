@@ -0,0 +1,123 @@
+//! A backend-neutral event source.
+//!
+//! The TUI can be built against either termion or crossterm (see the Cargo
+//! features of the same name). Both backends deliver key presses in their own
+//! types, so this module normalizes them into a single [`Key`]/[`Event`]
+//! stream the update loop in `main.rs` consumes without caring which backend
+//! produced it, mirroring how tui-rs ships parallel termion/crossterm demos.
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// A key the UI reacts to, normalized across terminal backends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    /// Any other key; the update loop ignores these.
+    Other,
+}
+
+/// An event delivered to the update loop.
+pub enum Event {
+    Input(Key),
+    Tick,
+}
+
+/// Configuration for the event source.
+#[derive(Clone, Copy)]
+pub struct Config {
+    /// How often a [`Event::Tick`] is emitted.
+    pub tick_rate: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { tick_rate: Duration::from_millis(250) }
+    }
+}
+
+/// A handle to the normalized event stream. A dedicated input thread reads the
+/// active backend and a tick thread paces redraws; both feed one channel.
+pub struct Events {
+    rx: mpsc::Receiver<Event>,
+    _input_handle: thread::JoinHandle<()>,
+    _tick_handle: thread::JoinHandle<()>,
+}
+
+impl Events {
+    pub fn with_config(config: Config) -> Events {
+        let (tx, rx) = mpsc::channel();
+
+        let input_handle = {
+            let tx = tx.clone();
+            thread::spawn(move || read_input(tx))
+        };
+
+        let tick_handle = thread::spawn(move || loop {
+            if tx.send(Event::Tick).is_err() {
+                break;
+            }
+            thread::sleep(config.tick_rate);
+        });
+
+        Events {
+            rx,
+            _input_handle: input_handle,
+            _tick_handle: tick_handle,
+        }
+    }
+
+    /// Blocks until the next event is available.
+    pub fn next(&self) -> Result<Event, mpsc::RecvError> {
+        self.rx.recv()
+    }
+}
+
+#[cfg(feature = "termion")]
+fn read_input(tx: mpsc::Sender<Event>) {
+    use std::io;
+    use termion::event::Key as TermionKey;
+    use termion::input::TermRead;
+
+    let stdin = io::stdin();
+    for key in stdin.keys().flatten() {
+        let key = match key {
+            TermionKey::Char(c) => Key::Char(c),
+            TermionKey::Left => Key::Left,
+            TermionKey::Right => Key::Right,
+            _ => Key::Other,
+        };
+        if tx.send(Event::Input(key)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(feature = "crossterm")]
+fn read_input(tx: mpsc::Sender<Event>) {
+    use crossterm::event::{self, Event as CEvent, KeyCode, KeyEventKind};
+
+    loop {
+        match event::read() {
+            // Windows crossterm reports both press and release; forward only
+            // presses so each keystroke isn't delivered twice.
+            Ok(CEvent::Key(key)) if key.kind == KeyEventKind::Press => {
+                let key = match key.code {
+                    KeyCode::Char(c) => Key::Char(c),
+                    KeyCode::Left => Key::Left,
+                    KeyCode::Right => Key::Right,
+                    _ => Key::Other,
+                };
+                if tx.send(Event::Input(key)).is_err() {
+                    return;
+                }
+            }
+            // Ignore non-key events (resize, mouse); bail out on a read error.
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+}
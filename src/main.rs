@@ -1,21 +1,55 @@
 use std::{
     path::Path,
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
     error::Error,
-    panic::{self, PanicInfo},
     io,
 };
+use clap::{ArgEnum, Parser};
 use env_logger::{Builder, Env};
 use log::info;
 
 #[macro_use]
 extern crate lazy_static;
 
-use backtrace::Backtrace;
+// Exactly one terminal backend must be selected.
+#[cfg(all(feature = "termion", feature = "crossterm"))]
+compile_error!("features `termion` and `crossterm` are mutually exclusive");
+#[cfg(not(any(feature = "termion", feature = "crossterm")))]
+compile_error!("one of the `termion` or `crossterm` features must be enabled");
+
+#[cfg(feature = "termion")]
+use termion::{input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
+#[cfg(feature = "termion")]
+use tui::backend::TermionBackend;
+
+#[cfg(feature = "crossterm")]
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+#[cfg(feature = "crossterm")]
+use tui::backend::CrosstermBackend;
+
+/// Restores the crossterm terminal on drop, so raw mode and the alternate
+/// screen are left behind no matter how `run()` exits (normal, `?`, or panic).
+#[cfg(feature = "crossterm")]
+struct CrosstermGuard;
+
+#[cfg(feature = "crossterm")]
+impl Drop for CrosstermGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            crossterm::cursor::Show
+        );
+    }
+}
 
-use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{
-    backend::TermionBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     symbols,
@@ -28,51 +62,230 @@ mod driver;
 use driver::*;
 
 mod aqi;
-use aqi::aqi_from_pm2_5;
+use aqi::{overall_aqi, sub_index, Pollutant, SubIndex};
 
 mod event;
 use event::*;
 
+mod store;
+use store::{Reading, Store};
+
+mod report;
+
+
+/// How the sensor should report measurements. Mirrors `driver::ReportMode`
+/// but derives `ArgEnum` so clap can parse it off the command line.
+#[derive(Copy, Clone, Debug, ArgEnum)]
+enum CliReportMode {
+    Initiative,
+    Query,
+}
+
+impl From<CliReportMode> for ReportMode {
+    fn from(mode: CliReportMode) -> Self {
+        match mode {
+            CliReportMode::Initiative => ReportMode::Initiative,
+            CliReportMode::Query => ReportMode::Query,
+        }
+    }
+}
+
+/// Air quality monitor for the SDS011 particulate sensor.
+#[derive(Debug, Parser)]
+#[clap(author, version, about)]
+struct Opts {
+    /// Serial device the sensor is connected to.
+    #[clap(long, default_value = "/dev/tty.usbserial-14110")]
+    device: String,
+
+    /// How often to poll the sensor and redraw, in milliseconds.
+    #[clap(long, default_value_t = 1000)]
+    tick_rate: u64,
+
+    /// How long to let the sensor settle during `configure`, in seconds.
+    #[clap(long, default_value_t = 1)]
+    configure_secs: u64,
+
+    /// Number of measurements kept in the visible chart window.
+    #[clap(long, default_value_t = 20)]
+    sample_window: usize,
+
+    /// Reporting mode requested from the sensor.
+    #[clap(long, arg_enum, default_value_t = CliReportMode::Initiative)]
+    report_mode: CliReportMode,
+
+    /// Use the `Dot` chart marker instead of `Braille` for terminals that
+    /// render Braille poorly.
+    #[clap(long)]
+    use_dot: bool,
+
+    /// CSV file readings are appended to and reloaded from on launch.
+    #[clap(long, default_value = "aq_history.csv")]
+    history: String,
+}
+
+
+/// The number of readings retained in memory for scroll-back. Older readings
+/// are dropped from the front once this many have accumulated; the persistent
+/// store keeps the full log.
+const RETAINED_HISTORY: usize = 10_000;
 
 struct App {
     sensor: Sensor,
+    store: Store,
+    history: Vec<Reading>,
     pm_2_5_data: Vec<(f64, f64)>,
+    pm_10_data: Vec<(f64, f64)>,
     window: [f64; 2],
+    sample_window: usize,
+    /// Index into `history` of the left edge of the visible window.
+    offset: usize,
+    /// When paused, ticks stop appending readings but the connection stays up.
+    paused: bool,
+    /// The most recent overall AQI and the pollutant responsible for it.
+    responsible: Option<SubIndex>,
+    /// Whether the window auto-follows the newest reading. Set false once the
+    /// user scrolls back so live ticks don't snap the view to the end.
+    following: bool,
 }
 
 impl App {
-    fn new(sensor: Sensor) -> App {
-        App {
+    fn new(sensor: Sensor, sample_window: usize, store: Store) -> App {
+        let mut history = store.load().unwrap_or_default();
+        if history.len() > RETAINED_HISTORY {
+            history.drain(..history.len() - RETAINED_HISTORY);
+        }
+        let mut app = App {
             sensor,
+            store,
+            history,
             pm_2_5_data: vec![],
-            window: [0.0, 20.0],
-        }
+            pm_10_data: vec![],
+            window: [0.0, sample_window as f64],
+            sample_window,
+            offset: 0,
+            paused: false,
+            responsible: None,
+            following: true,
+        };
+        app.scroll_to_end();
+        app
     }
 
     fn update(&mut self) {
+        if self.paused {
+            return
+        }
+
         let measurement = self.sensor.get_measurement()
             .expect("Failed to get measurement");
-        let aqi = aqi_from_pm2_5(measurement.pm2_5);
 
-        if self.pm_2_5_data.len() > 20 {
-            self.pm_2_5_data.remove(0);
-            self.window[0] += 1.0;
-            self.window[1] += 1.0;
-            self.pm_2_5_data.push((self.window[1], aqi as f64));
+        // The overall AQI is the worst of the per-pollutant sub-indices; keep
+        // the responsible pollutant around so the chart title can name it.
+        let overall = overall_aqi(&[
+            (Pollutant::Pm2_5, measurement.pm2_5),
+            (Pollutant::Pm10, measurement.pm10),
+        ])
+        .expect("at least one pollutant");
+        self.responsible = Some(overall);
+
+        let reading = Reading {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            pm2_5: measurement.pm2_5,
+            pm10: measurement.pm10,
+            aqi: overall.aqi,
+        };
+
+        if let Err(err) = self.store.append(&reading) {
+            info!("Failed to persist reading: {}", err);
+        }
+
+        if self.history.len() >= RETAINED_HISTORY {
+            self.history.remove(0);
+            // Every retained index just shifted down by one; keep a scrolled
+            // back view pinned to the same readings.
+            if !self.following {
+                self.offset = self.offset.saturating_sub(1);
+            }
+        }
+        self.history.push(reading);
+
+        // Only chase the tail when the user hasn't scrolled away from it.
+        if self.following {
+            self.scroll_to_end();
         } else {
-            self.pm_2_5_data.push((self.pm_2_5_data.len() as f64, aqi as f64));
+            self.refresh_window();
+        }
+    }
+
+    /// Freezes/unfreezes sampling without dropping the sensor connection.
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Clears the retained series and resets the scroll window. This also
+    /// truncates the persistent store so "cleared" readings don't reload on
+    /// the next launch.
+    fn reset(&mut self) {
+        self.history.clear();
+        if let Err(err) = self.store.clear() {
+            info!("Failed to clear store: {}", err);
         }
+        self.scroll_to_end();
+    }
+
+    fn scroll_left(&mut self) {
+        self.following = false;
+        self.offset = self.offset.saturating_sub(1);
+        self.refresh_window();
+    }
+
+    fn scroll_right(&mut self) {
+        let max_offset = self.history.len().saturating_sub(self.sample_window);
+        if self.offset < max_offset {
+            self.offset += 1;
+        }
+        // Reaching the tail re-enables auto-follow.
+        if self.offset >= max_offset {
+            self.following = true;
+        }
+        self.refresh_window();
+    }
+
+    fn scroll_to_end(&mut self) {
+        self.offset = self.history.len().saturating_sub(self.sample_window);
+        self.following = true;
+        self.refresh_window();
+    }
+
+    /// Rebuilds the visible datasets from the retained history at the current
+    /// scroll offset.
+    fn refresh_window(&mut self) {
+        let end = (self.offset + self.sample_window).min(self.history.len());
+        self.pm_2_5_data.clear();
+        self.pm_10_data.clear();
+        for (i, reading) in self.history[self.offset..end].iter().enumerate() {
+            let x = (self.offset + i) as f64;
+            self.pm_2_5_data.push((x, sub_index(Pollutant::Pm2_5, reading.pm2_5).aqi as f64));
+            self.pm_10_data.push((x, sub_index(Pollutant::Pm10, reading.pm10).aqi as f64));
+        }
+        self.window = [self.offset as f64, (self.offset + self.sample_window) as f64];
     }
 }
 
 
 fn run() -> Result<(), Box<dyn Error>> {
-    let path = Path::new("/dev/tty.usbserial-14110");
+    let opts = Opts::parse();
+
+    let path = Path::new(&opts.device);
     let mut sensor = Sensor::new(path)
         .expect("Unable to open device");
     info!("Opened device at path: {:?}", path);
 
-    sensor.configure(Duration::from_secs(1))
+    sensor.configure(Duration::from_secs(opts.configure_secs))
         .expect("Failed to configure device");
     info!("Configured device");
 
@@ -80,21 +293,47 @@ fn run() -> Result<(), Box<dyn Error>> {
     let wake_command = SendData::set_work_state(WorkState::Measuring);
     sensor.send(&wake_command).expect("Failed to send wake command");
     // Set the report mode
-    sensor.send(&SendData::set_report_mode(ReportMode::Initiative))
-        .expect("Failed to set report mode to initiative");
+    sensor.send(&SendData::set_report_mode(opts.report_mode.into()))
+        .expect("Failed to set report mode");
 
-    let mut app = App::new(sensor);
+    let store = Store::open(&opts.history)?;
+    let mut app = App::new(sensor, opts.sample_window, store);
 
-    // Initialize the terminal
-    let stdout = io::stdout().into_raw_mode()?;
-    let stdout = MouseTerminal::from(stdout);
-    let stdout = AlternateScreen::from(stdout);
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let marker = if opts.use_dot {
+        symbols::Marker::Dot
+    } else {
+        symbols::Marker::Braille
+    };
+
+    // Initialize the terminal. Each backend enters raw mode and the alternate
+    // screen through its own API, but both yield a `tui::Terminal` the draw
+    // closure below is agnostic to.
+    #[cfg(feature = "termion")]
+    let mut terminal = {
+        let stdout = io::stdout().into_raw_mode()?;
+        let stdout = MouseTerminal::from(stdout);
+        let stdout = AlternateScreen::from(stdout);
+        let backend = TermionBackend::new(stdout);
+        Terminal::new(backend)?
+    };
+    #[cfg(feature = "crossterm")]
+    let mut terminal = {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        Terminal::new(backend)?
+    };
+    // Restore the terminal however `run()` exits. Termion's wrappers do this
+    // on drop; crossterm has no such guards, so an early `?` or a panic would
+    // otherwise leave raw mode on the alternate screen. The guard's `Drop`
+    // runs on the normal, error, and unwind paths alike.
+    #[cfg(feature = "crossterm")]
+    let _restore = CrosstermGuard;
 
     // Setup event handlers
     let config = Config {
-        tick_rate: Duration::from_secs(1),
+        tick_rate: Duration::from_millis(opts.tick_rate),
         ..Default::default()
     };
     let events = Events::with_config(config);
@@ -126,18 +365,34 @@ fn run() -> Result<(), Box<dyn Error>> {
                 ),
             ];
 
-            let datasets = vec![Dataset::default()
-                                .name("data")
-                                .marker(symbols::Marker::Braille)
-                                .style(Style::default().fg(Color::Yellow))
-                                .graph_type(GraphType::Line)
-                                .data(&app.pm_2_5_data)];
+            let datasets = vec![
+                Dataset::default()
+                    .name("PM2.5")
+                    .marker(marker)
+                    .style(Style::default().fg(Color::Yellow))
+                    .graph_type(GraphType::Line)
+                    .data(&app.pm_2_5_data),
+                Dataset::default()
+                    .name("PM10")
+                    .marker(marker)
+                    .style(Style::default().fg(Color::Magenta))
+                    .graph_type(GraphType::Line)
+                    .data(&app.pm_10_data),
+            ];
+
+            let title = match app.responsible {
+                Some(s) => format!(
+                    "Air Quality Index {} ({}, {})",
+                    s.aqi, s.pollutant, s.category
+                ),
+                None => "Air Quality Index (PM2.5 / PM10)".to_string(),
+            };
 
             let chart = Chart::new(datasets)
                 .block(
                     Block::default()
                         .title(Span::styled(
-                            "Air Quality Index (PM 2.5)",
+                            title,
                             Style::default()
                                 .fg(Color::Cyan)
                                 .add_modifier(Modifier::BOLD),
@@ -169,45 +424,32 @@ fn run() -> Result<(), Box<dyn Error>> {
 
         match events.next()? {
             Event::Tick => app.update(),
-            Event::Input(Key::Char('q')) => {
-                break;
+            // `Key` is normalized by the event source, so the same arms serve
+            // either backend.
+            Event::Input(key) => match key {
+                Key::Char('q') => break,
+                Key::Char('p') | Key::Char(' ') => app.toggle_pause(),
+                Key::Char('r') => app.reset(),
+                Key::Left => app.scroll_left(),
+                Key::Right => app.scroll_right(),
+                _ => (),
             },
-            Event::Input(_) => (),
         };
     }
 
+    // The crossterm terminal is restored by `CrosstermGuard` on drop.
     Ok(())
 }
 
-/// Shows a backtrace if the program panics
-fn panic_hook(info: &PanicInfo<'_>) {
-    if cfg!(debug_assertions) {
-        let location = info.location().unwrap();
-
-        let msg = match info.payload().downcast_ref::<&'static str>() {
-            Some(s) => *s,
-            None => match info.payload().downcast_ref::<String>() {
-                Some(s) => &s[..],
-                None => "Box<Any>",
-            },
-        };
-
-        let stacktrace: String = format!("{:?}", Backtrace::new()).replace('\n', "\n\r");
-
-        println!(
-            "{}thread '<unnamed>' panicked at '{}', {}\n\r{}",
-            termion::screen::ToMainScreen,
-            msg,
-            location,
-            stacktrace
-        );
-    }
-}
-
 fn main() -> Result<(), Box<dyn Error>> {
-    panic::set_hook(Box::new(|info| {
-        panic_hook(info);
-    }));
+    // Install the panic reporting hook. It returns to the main screen before
+    // printing, gates the backtrace on RUST_BACKTRACE, and attaches hints for
+    // the most common failure: a misconfigured serial device.
+    report::HookBuilder::new()
+        .add_suggestion(
+            "Check that the sensor is connected at the configured device path.",
+        )
+        .install()?;
 
     // Set up logger environment
     Builder::from_env(Env::default().default_filter_or("trace"))
@@ -215,5 +457,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         .unwrap_or_else(|err| eprintln!("env_logger::init() failed: {}", err));
 
 
-    run()
+    // Render any error returned from the TUI loop through the installed error
+    // hook so it lands on the main screen with the same sections and theme.
+    if let Err(err) = run() {
+        report::report_error(err.as_ref());
+        std::process::exit(1);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,331 @@
+//! Panic and error reporting modeled on color-eyre's configurable hooks.
+//!
+//! The sensor binary spends most of its life inside a TUI drawing to the
+//! alternate screen, so an unhandled panic (a serial device that can't be
+//! opened, a measurement that fails to parse) used to leave a raw
+//! `.expect()` message smeared across the chart. This module installs a
+//! panic hook and an error hook that first restore the terminal (leave the
+//! alternate screen, and under crossterm disable raw mode too), then print a
+//! themeable, sectioned report and only capture a backtrace when the usual
+//! `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE` environment variables ask for one.
+//!
+//! Rendering uses plain ANSI escapes rather than a backend's styling types so
+//! the module compiles under either the `termion` or `crossterm` feature.
+
+use std::{
+    env,
+    error::Error,
+    fmt::{self, Write as _},
+    panic::{self, PanicInfo},
+    sync::{Arc, Mutex},
+};
+
+use backtrace::Backtrace;
+
+/// Select Graphic Rendition escapes, used in place of a backend's styling
+/// types so this module stays backend-neutral.
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+const FG_RESET: &str = "\x1b[39m";
+
+lazy_static! {
+    /// The installed error hook. Mirrors color-eyre's global handler: once a
+    /// `HookBuilder` is installed, [`report_error`] renders `Box<dyn Error>`s
+    /// through it with the same theme and sections as the panic hook.
+    static ref ERROR_HOOK: Mutex<Option<Arc<Hook>>> = Mutex::new(None);
+}
+
+/// Restores the terminal before a report is printed. Each backend undoes its
+/// own setup from `run()`: termion's guards drop on unwind, so we only need to
+/// leave the alternate screen, while crossterm must also disable raw mode.
+#[cfg(feature = "termion")]
+fn restore_terminal() {
+    // `\x1b[?1049l` leaves the alternate screen (termion's `ToMainScreen`).
+    print!("\x1b[?1049l");
+}
+
+#[cfg(feature = "crossterm")]
+fn restore_terminal() {
+    use std::io;
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(io::stdout(), crossterm::terminal::LeaveAlternateScreen);
+}
+
+/// Extracts the human readable payload from a [`PanicInfo`], falling back to
+/// `Box<Any>` for payloads we can't downcast.
+fn payload_message<'a>(info: &'a PanicInfo<'_>) -> &'a str {
+    match info.payload().downcast_ref::<&'static str>() {
+        Some(s) => s,
+        None => match info.payload().downcast_ref::<String>() {
+            Some(s) => s.as_str(),
+            None => "Box<Any>",
+        },
+    }
+}
+
+/// A themeable foreground color, stored as the raw ANSI escape that selects it
+/// so the report can be rendered without depending on a backend's color type.
+#[derive(Clone)]
+pub struct ThemeColor(String);
+
+impl ThemeColor {
+    /// Wraps a foreground escape sequence (e.g. `"\x1b[31m"` for red).
+    pub fn new<S: Into<String>>(fg: S) -> Self {
+        ThemeColor(fg.into())
+    }
+
+    fn fg(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Colors used when rendering a report. A theme can mix foreground colors
+/// freely across the message headline, the panic location, and the frames.
+pub struct Theme {
+    pub message: ThemeColor,
+    pub location: ThemeColor,
+    pub frames: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            message: ThemeColor::new("\x1b[31m"),  // red
+            location: ThemeColor::new("\x1b[36m"), // cyan
+            frames: ThemeColor::new("\x1b[90m"),   // bright black
+        }
+    }
+}
+
+/// Formats the headline of a panic report. Implementors receive the panic
+/// payload message and may decorate it however they like.
+pub trait PanicMessage: Send + Sync {
+    fn display(&self, message: &str, out: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+/// The default headline: a bold "The application panicked." line in the
+/// theme's message color, followed by the payload message.
+struct DefaultPanicMessage {
+    color: ThemeColor,
+}
+
+impl PanicMessage for DefaultPanicMessage {
+    fn display(&self, message: &str, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(
+            out,
+            "{}{}The application panicked.{}",
+            self.color.fg(),
+            BOLD,
+            RESET
+        )?;
+        write!(out, "\n\r{}Message:{} {}", BOLD, RESET, message)
+    }
+}
+
+/// An arbitrary note rendered beneath the error, e.g. a suggestion to check
+/// that the sensor is connected at the configured device path.
+struct Section {
+    header: &'static str,
+    body: String,
+}
+
+/// Builds and installs the panic and error reporting hooks.
+///
+/// Mirrors color-eyre's `HookBuilder`: register a custom [`PanicMessage`],
+/// attach `note`/`suggestion` sections, pick a [`Theme`], then `install()`.
+pub struct HookBuilder {
+    theme: Theme,
+    panic_message: Option<Box<dyn PanicMessage>>,
+    sections: Vec<Section>,
+}
+
+impl Default for HookBuilder {
+    fn default() -> Self {
+        HookBuilder {
+            theme: Theme::default(),
+            // `None` means "derive the default headline from the final theme
+            // at `install()` time", so `.theme(...)` can recolor it.
+            panic_message: None,
+            sections: Vec::new(),
+        }
+    }
+}
+
+impl HookBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override how the report headline is rendered.
+    pub fn panic_message<M: PanicMessage + 'static>(mut self, message: M) -> Self {
+        self.panic_message = Some(Box::new(message));
+        self
+    }
+
+    /// Replace the color theme.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Attach a free-form note rendered under the error.
+    pub fn add_note<S: Into<String>>(mut self, body: S) -> Self {
+        self.sections.push(Section { header: "Note", body: body.into() });
+        self
+    }
+
+    /// Attach an actionable suggestion rendered under the error.
+    pub fn add_suggestion<S: Into<String>>(mut self, body: S) -> Self {
+        self.sections.push(Section { header: "Suggestion", body: body.into() });
+        self
+    }
+
+    /// Install both the panic hook and the error hook, consuming the builder.
+    pub fn install(self) -> Result<(), Box<dyn Error>> {
+        let hook = Arc::new(self.into_hook());
+
+        // The error hook: stash the config so `report_error` can render
+        // errors returned from `main` with the same theme and sections.
+        *ERROR_HOOK.lock().unwrap() = Some(hook.clone());
+
+        // The panic hook.
+        let panic_hook = hook;
+        panic::set_hook(Box::new(move |info| {
+            // Restore the terminal first so the report lands on the main
+            // screen instead of on top of the chart.
+            restore_terminal();
+            let rendered = panic_hook.render_panic(info);
+            // Use `\n\r` throughout: the terminal may still be in raw mode
+            // when a panic fires from inside the TUI loop.
+            print!("{}", rendered.replace('\n', "\n\r"));
+        }));
+        Ok(())
+    }
+
+    fn into_hook(self) -> Hook {
+        let panic_message = self.panic_message.unwrap_or_else(|| {
+            Box::new(DefaultPanicMessage { color: self.theme.message.clone() })
+        });
+        Hook {
+            theme: self.theme,
+            panic_message,
+            sections: self.sections,
+        }
+    }
+}
+
+/// Renders an error returned from `main` through the installed error hook and
+/// prints it once the terminal has been restored. Falls back to a plain
+/// message if no hook has been installed.
+pub fn report_error(err: &(dyn Error + 'static)) {
+    restore_terminal();
+    match ERROR_HOOK.lock().unwrap().as_ref() {
+        Some(hook) => print!("{}", hook.render_error(err).replace('\n', "\n\r")),
+        None => eprintln!("Error: {}", err),
+    }
+}
+
+/// The installed reporting configuration.
+struct Hook {
+    theme: Theme,
+    panic_message: Box<dyn PanicMessage>,
+    sections: Vec<Section>,
+}
+
+impl Hook {
+    /// Renders a full panic report as a string (without the terminal restore,
+    /// which the hook emits separately).
+    fn render_panic(&self, info: &PanicInfo<'_>) -> String {
+        let mut out = String::new();
+        let message = payload_message(info);
+        let _ = self.panic_message.display(message, &mut out);
+
+        if let Some(location) = info.location() {
+            let _ = write!(
+                out,
+                "\n{}Location:{} {}{}{}",
+                BOLD,
+                RESET,
+                self.theme.location.fg(),
+                location,
+                FG_RESET,
+            );
+        }
+
+        self.render_sections(&mut out);
+        self.render_backtrace(&mut out);
+
+        out.push('\n');
+        out
+    }
+
+    /// Renders an error report, including the error's source chain.
+    fn render_error(&self, err: &(dyn Error + 'static)) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{}{}The application errored.{}",
+            self.theme.message.fg(),
+            BOLD,
+            RESET
+        );
+        let _ = write!(out, "\n{}Error:{} {}", BOLD, RESET, err);
+
+        let mut source = err.source();
+        while let Some(cause) = source {
+            let _ = write!(out, "\n{}Caused by:{} {}", BOLD, RESET, cause);
+            source = cause.source();
+        }
+
+        self.render_sections(&mut out);
+        self.render_backtrace(&mut out);
+
+        out.push('\n');
+        out
+    }
+
+    fn render_sections(&self, out: &mut String) {
+        for section in &self.sections {
+            let _ = write!(
+                out,
+                "\n\n{}{}:{} {}",
+                BOLD, section.header, RESET, section.body
+            );
+        }
+    }
+
+    fn render_backtrace(&self, out: &mut String) {
+        match self.capture_backtrace() {
+            Some(bt) => {
+                let _ = write!(
+                    out,
+                    "\n\n{}{:?}{}",
+                    self.theme.frames.fg(),
+                    bt,
+                    FG_RESET,
+                );
+            }
+            None => {
+                let _ = write!(
+                    out,
+                    "\n\nRun with RUST_BACKTRACE=1 to show a backtrace."
+                );
+            }
+        }
+    }
+
+    /// Captures a backtrace only when `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`
+    /// is set to a non-empty value other than `0`.
+    fn capture_backtrace(&self) -> Option<Backtrace> {
+        if backtrace_enabled() {
+            Some(Backtrace::new())
+        } else {
+            None
+        }
+    }
+}
+
+fn backtrace_enabled() -> bool {
+    let enabled = |var: &str| matches!(env::var(var), Ok(v) if v != "0" && !v.is_empty());
+    enabled("RUST_LIB_BACKTRACE") || enabled("RUST_BACKTRACE")
+}
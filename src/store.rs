@@ -0,0 +1,97 @@
+//! A tiny CSV-backed log of sensor readings.
+//!
+//! Every measurement the App records is appended here so the history survives
+//! restarts and can be reloaded into the scroll-back window on launch, turning
+//! the live view into a logger.
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// A single logged reading. `timestamp` is seconds since the Unix epoch.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reading {
+    pub timestamp: u64,
+    pub pm2_5: f32,
+    pub pm10: f32,
+    pub aqi: i32,
+}
+
+/// An append-only CSV store of readings.
+pub struct Store {
+    path: PathBuf,
+}
+
+impl Store {
+    /// Opens (creating if necessary) the store at `path`, writing a header
+    /// row the first time the file is created.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Store, Box<dyn Error>> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            let mut file = File::create(&path)?;
+            writeln!(file, "timestamp,pm2_5,pm10,aqi")?;
+        }
+        Ok(Store { path })
+    }
+
+    /// Appends a reading to the log.
+    pub fn append(&mut self, reading: &Reading) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(
+            file,
+            "{},{},{},{}",
+            reading.timestamp, reading.pm2_5, reading.pm10, reading.aqi
+        )?;
+        Ok(())
+    }
+
+    /// Truncates the log back to just its header row, discarding all readings.
+    pub fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(&self.path)?;
+        writeln!(file, "timestamp,pm2_5,pm10,aqi")?;
+        Ok(())
+    }
+
+    /// Loads every previously logged reading, skipping the header row. Rows
+    /// that fail to parse are ignored so a partially written tail line can't
+    /// take the whole history down.
+    pub fn load(&self) -> Result<Vec<Reading>, Box<dyn Error>> {
+        let file = File::open(&self.path)?;
+        let reader = BufReader::new(file);
+        let mut readings = vec![];
+        for line in reader.lines().skip(1) {
+            let line = line?;
+            if let Some(reading) = parse_row(&line) {
+                readings.push(reading);
+            }
+        }
+        Ok(readings)
+    }
+}
+
+fn parse_row(line: &str) -> Option<Reading> {
+    let mut fields = line.split(',');
+    Some(Reading {
+        timestamp: fields.next()?.parse().ok()?,
+        pm2_5: fields.next()?.parse().ok()?,
+        pm10: fields.next()?.parse().ok()?,
+        aqi: fields.next()?.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod test_store {
+    use super::*;
+
+    #[test]
+    fn test_parses_logged_row() {
+        let reading = Reading { timestamp: 1, pm2_5: 12.0, pm10: 20.0, aqi: 50 };
+        assert_eq!(Some(reading), parse_row("1,12,20,50"));
+    }
+
+    #[test]
+    fn test_ignores_malformed_row() {
+        assert_eq!(None, parse_row("not,a,reading"));
+    }
+}